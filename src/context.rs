@@ -0,0 +1 @@
+pub mod shader_context;
@@ -1,66 +1,56 @@
-use std::ffi::CString;
-use std::sync::mpsc::Receiver;
+use std::path::Path;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use crate::graphics::{
     buffers::create_vertex_buffers,
-    shaders::{compile_shader_program, create_default_shaders},
+    input::InputState,
+    offscreen::OffscreenTarget,
+    particles::ParticleSystem,
+    passes::{self, Pass},
+    shaders::{
+        self, compile_compute_program, compile_shader_program, create_default_shaders,
+        ShaderStagePaths,
+    },
+    textures::{self, Channel, MAX_CHANNELS},
     time::TimeState,
+    uniforms::{self, UniformMap},
 };
-use glfw::{Action, Context, Key};
 
-// Manages the OpenGL context, window, and rendering state
+// Number of particles simulated by the optional compute pipeline.
+const PARTICLE_COUNT: u32 = 65_536;
+
+// Owns the shader program, vertex buffers, texture channels, optional compute/particle
+// pipeline, and timing state for the quad being rendered. Assumes a current OpenGL
+// context already exists (see `Window`).
 pub struct ShaderContext {
-    pub window: glfw::Window,                       // GLFW window instance
-    pub events: Receiver<(f64, glfw::WindowEvent)>, // Event receiver for window events
-    pub glfw: glfw::Glfw,                           // GLFW instance
-    pub shader_program: u32,                        // OpenGL shader program ID
-    pub vao: u32,                                   // Vertex Array Object ID
-    pub vbo: u32,                                   // Vertex Buffer Object ID
-    pub time_state: TimeState,                      // Timing state for animations
-    pub vertex_shader_path: String,                 // Path to the vertex shader file
-    pub fragment_shader_path: String,               // Path to the fragment shader file
+    pub shader_program: u32,              // OpenGL shader program ID
+    pub uniforms: UniformMap,             // Cached active-uniform locations
+    pub vao: u32,                         // Vertex Array Object ID
+    pub vbo: u32,                         // Vertex Buffer Object ID
+    pub channels: Vec<Channel>,           // Loaded u_channel0..u_channel3 textures
+    pub compute_program: Option<u32>,     // Optional compute program driving `particles`
+    pub compute_uniforms: UniformMap,     // Cached uniform locations for `compute_program`
+    pub particle_program: Option<u32>,    // Optional vertex+fragment program that draws `particles`
+    pub particles: Option<ParticleSystem>, // SSBO-backed particle buffer, once the pipeline exists
+    pub time_state: TimeState,            // Timing state for animations
+    pub input: InputState,                // Tracked mouse/keyboard state for u_mouse/u_keyboard
+    pub vertex_shader_path: String,       // Path to the vertex shader file
+    pub fragment_shader_path: String,      // Path to the fragment shader file
+    export_target: Option<OffscreenTarget>, // Lazily-sized FBO used by `render_offscreen`
+    passes: Vec<Pass>,                    // Ordered Buffer A..D feedback passes, if any
 }
 
 impl ShaderContext {
-    // Creates a new ShaderContext instance
+    // Creates a new ShaderContext instance. Requires a current GL context.
+    // `channel_paths` are loaded, in order, as u_channel0..u_channel3; any path
+    // beyond `MAX_CHANNELS` is ignored, and any path that fails to decode is
+    // skipped (logged), leaving its u_channelN slot with texture 0, rather than
+    // aborting startup.
     pub fn new(
-        width: u32,
-        height: u32,
-        title: &str,
         vertex_shader_path: String,
         fragment_shader_path: String,
+        channel_paths: &[String],
     ) -> Self {
-        // Initialize GLFW
-        let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
-
-        // Set OpenGL version and profile
-        glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
-        glfw.window_hint(glfw::WindowHint::OpenGlProfile(
-            glfw::OpenGlProfileHint::Core,
-        ));
-
-        // Enable double buffering
-        glfw.window_hint(glfw::WindowHint::DoubleBuffer(true));
-
-        // macOS compatibility
-        #[cfg(target_os = "macos")]
-        glfw.window_hint(glfw::WindowHint::OpenGlForwardCompat(true));
-
-        // Create a windowed mode window and its OpenGL context
-        let (mut window, events) = glfw
-            .create_window(width, height, title, glfw::WindowMode::Windowed)
-            .expect("Failed to create GLFW window");
-
-        // Enable VSync
-        window.make_current();
-        glfw.set_swap_interval(glfw::SwapInterval::Sync(1));
-        window.set_key_polling(true);
-        window.set_framebuffer_size_polling(true);
-
-        // Load OpenGL function pointers
-        gl::load_with(|s| window.get_proc_address(s) as *const _);
-
         // Create and bind VAO and VBO
         let (vao, vbo) = create_vertex_buffers();
 
@@ -68,18 +58,50 @@ impl ShaderContext {
         create_default_shaders(&vertex_shader_path, &fragment_shader_path);
 
         // Compile and link shaders into a program
-        let shader_program = compile_shader_program(&vertex_shader_path, &fragment_shader_path);
+        let stages = ShaderStagePaths::discover(vertex_shader_path.clone(), fragment_shader_path.clone());
+        let shader_program = compile_shader_program(&stages)
+            .unwrap_or_else(|e| panic!("Failed to compile initial shader program: {}", e));
+        let uniforms = uniforms::reflect_uniforms(shader_program);
+
+        let channels = channel_paths
+            .iter()
+            .take(MAX_CHANNELS)
+            .map(|path| {
+                textures::load_channel(path).unwrap_or(Channel {
+                    texture: 0,
+                    width: 0,
+                    height: 0,
+                })
+            })
+            .collect();
+
+        let shader_dir = Path::new(&vertex_shader_path)
+            .parent()
+            .unwrap_or_else(|| Path::new("."))
+            .to_path_buf();
+        let (compute_program, compute_uniforms, particle_program, particles) =
+            load_particle_pipeline(&shader_dir);
+
+        // Pass buffers are reallocated to the real resolution on the first `draw` call,
+        // so the size passed in here doesn't matter.
+        let passes = passes::discover_passes(&shader_dir, &vertex_shader_path, 1, 1);
 
         ShaderContext {
-            window,
-            events,
-            glfw,
             shader_program,
+            uniforms,
             vao,
             vbo,
+            channels,
+            compute_program,
+            compute_uniforms,
+            particle_program,
+            particles,
             time_state: TimeState::new(),
+            input: InputState::new(),
             vertex_shader_path,
             fragment_shader_path,
+            export_target: None,
+            passes,
         }
     }
 
@@ -87,19 +109,93 @@ impl ShaderContext {
     pub fn reload_shaders(&mut self) {
         println!("Reloading shaders...");
 
-        // Recompile shader program
-        let new_program =
-            compile_shader_program(&self.vertex_shader_path, &self.fragment_shader_path);
+        // Recompile shader program, keeping the currently running one on failure
+        let stages =
+            ShaderStagePaths::discover(self.vertex_shader_path.clone(), self.fragment_shader_path.clone());
+        match compile_shader_program(&stages) {
+            Ok(new_program) => {
+                unsafe {
+                    gl::DeleteProgram(self.shader_program);
+                }
+                self.shader_program = new_program;
+                self.uniforms = uniforms::reflect_uniforms(new_program);
+                println!("Shaders reloaded successfully");
+            }
+            Err(e) => {
+                eprintln!(
+                    "Failed to reload shaders ({}, {}): {}",
+                    self.vertex_shader_path, self.fragment_shader_path, e
+                );
+            }
+        }
+
+        self.reload_particle_pipeline();
+
+        let shader_dir = Path::new(&self.vertex_shader_path)
+            .parent()
+            .unwrap_or_else(|| Path::new("."))
+            .to_path_buf();
+        let (width, height) = self
+            .passes
+            .first()
+            .map(|p| p.resolution())
+            .unwrap_or((1, 1));
+        passes::reload_passes(&mut self.passes, &shader_dir, &self.vertex_shader_path, width, height);
+    }
 
-        // Replace the old program if compilation succeeds
-        if new_program != 0 {
-            unsafe {
-                gl::DeleteProgram(self.shader_program);
+    // Recompiles the compute/particle programs if their source files are present, keeping
+    // the currently running ones on failure. The particle buffer itself is preserved across
+    // reloads (only created the first time the pipeline becomes available) so the
+    // simulation doesn't reset every time a shader is saved.
+    fn reload_particle_pipeline(&mut self) {
+        let shader_dir = Path::new(&self.vertex_shader_path)
+            .parent()
+            .unwrap_or_else(|| Path::new("."));
+        let compute_path = shaders::optional_stage_path(shader_dir, "compute.glsl");
+        let particle_vertex_path = shaders::optional_stage_path(shader_dir, "particle_vertex.glsl");
+        let particle_fragment_path = shaders::optional_stage_path(shader_dir, "particle_fragment.glsl");
+
+        let (compute_path, particle_vertex_path, particle_fragment_path) =
+            match (compute_path, particle_vertex_path, particle_fragment_path) {
+                (Some(c), Some(v), Some(f)) => (c, v, f),
+                _ => return,
+            };
+
+        match (
+            compile_compute_program(&compute_path),
+            compile_shader_program(&ShaderStagePaths::new(particle_vertex_path, particle_fragment_path)),
+        ) {
+            (Ok(new_compute), Ok(new_particle)) => {
+                if let Some(old) = self.compute_program.take() {
+                    unsafe {
+                        gl::DeleteProgram(old);
+                    }
+                }
+                if let Some(old) = self.particle_program.take() {
+                    unsafe {
+                        gl::DeleteProgram(old);
+                    }
+                }
+
+                self.compute_uniforms = uniforms::reflect_uniforms(new_compute);
+                self.compute_program = Some(new_compute);
+                self.particle_program = Some(new_particle);
+                if self.particles.is_none() {
+                    self.particles = Some(ParticleSystem::new(PARTICLE_COUNT));
+                }
+            }
+            (compute_result, particle_result) => {
+                // Only one side may have compiled; delete it so the orphaned program
+                // object isn't leaked, since neither replaces a currently running one.
+                match compute_result {
+                    Ok(new_compute) => unsafe { gl::DeleteProgram(new_compute) },
+                    Err(e) => eprintln!("Failed to reload compute shader: {}", e),
+                }
+                match particle_result {
+                    Ok(new_particle) => unsafe { gl::DeleteProgram(new_particle) },
+                    Err(e) => eprintln!("Failed to reload particle shaders: {}", e),
+                }
             }
-            self.shader_program = new_program;
-            println!("Shaders reloaded successfully");
-        } else {
-            println!("Failed to reload shaders");
         }
     }
 
@@ -108,83 +204,120 @@ impl ShaderContext {
         self.time_state.update();
     }
 
-    // Renders a frame
-    pub fn render(&mut self) {
+    // Renders a frame to the window's default framebuffer at the given resolution
+    pub fn render(&mut self, width: i32, height: i32) {
+        self.draw(0, width, height);
+    }
+
+    // Renders a frame into an offscreen FBO at `width`x`height` (independent of the
+    // window size, or with no window at all) and reads the result back as top-to-bottom
+    // RGBA8 rows, for the headless frame-export mode. The FBO is cached and only
+    // recreated when the requested resolution changes.
+    pub fn render_offscreen(&mut self, width: u32, height: u32) -> Vec<u8> {
+        let needs_resize = match &self.export_target {
+            Some(target) => target.width != width || target.height != height,
+            None => true,
+        };
+        if needs_resize {
+            self.export_target = Some(OffscreenTarget::new(width, height));
+        }
+        let fbo = self.export_target.as_ref().unwrap().fbo;
+        self.draw(fbo, width as i32, height as i32);
+
+        self.export_target.as_ref().unwrap().read_pixels()
+    }
+
+    // Shared draw path for `render` and `render_offscreen`: issues the draw calls into
+    // `framebuffer` (0 for the window's default framebuffer).
+    fn draw(&mut self, framebuffer: u32, width: i32, height: i32) {
+        // Run the Buffer A..D feedback pass graph first, since the final on-screen pass
+        // (and later buffers) may sample this frame's results from earlier buffers.
+        if !self.passes.is_empty() {
+            let time_state = &self.time_state;
+            passes::render_passes(&mut self.passes, self.vao, width as u32, height as u32, |uniforms| {
+                uniforms::set_f32(uniforms, "u_time", time_state.total_time);
+                uniforms::set_f32(uniforms, "u_deltaTime", time_state.delta_time);
+                uniforms::set_vec2(uniforms, "u_resolution", width as f32, height as f32);
+            });
+        }
+
         unsafe {
+            // `render_passes` leaves the default framebuffer bound; rebind the real target.
+            gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer);
+
             // Clear the screen
+            gl::Viewport(0, 0, width, height);
             gl::ClearColor(0.1, 0.1, 0.1, 1.0);
             gl::Clear(gl::COLOR_BUFFER_BIT);
 
             // Use the shader program
             gl::UseProgram(self.shader_program);
 
-            // Update time uniforms
-            let time_loc = gl::GetUniformLocation(
-                self.shader_program,
-                CString::new("u_time").unwrap().as_ptr(),
-            );
-            gl::Uniform1f(time_loc, self.time_state.total_time);
-
-            let delta_loc = gl::GetUniformLocation(
-                self.shader_program,
-                CString::new("u_deltaTime").unwrap().as_ptr(),
-            );
-            gl::Uniform1f(delta_loc, self.time_state.delta_time);
-
-            let epoch_loc = gl::GetUniformLocation(
-                self.shader_program,
-                CString::new("u_epochTime").unwrap().as_ptr(),
-            );
+            // Update time uniforms from cached locations
+            uniforms::set_f32(&self.uniforms, "u_time", self.time_state.total_time);
+            uniforms::set_f32(&self.uniforms, "u_deltaTime", self.time_state.delta_time);
+
             let epoch_secs = SystemTime::now()
                 .duration_since(UNIX_EPOCH)
                 .unwrap_or(Duration::from_secs(0))
                 .as_secs_f32();
-            gl::Uniform1f(epoch_loc, epoch_secs);
+            uniforms::set_f32(&self.uniforms, "u_epochTime", epoch_secs);
 
             // Update resolution uniform
-            let (width, height) = self.window.get_framebuffer_size();
-            let resolution_loc = gl::GetUniformLocation(
-                self.shader_program,
-                CString::new("u_resolution").unwrap().as_ptr(),
-            );
-            gl::Uniform2f(resolution_loc, width as f32, height as f32);
+            uniforms::set_vec2(&self.uniforms, "u_resolution", width as f32, height as f32);
+
+            // Update mouse/keyboard interaction uniforms
+            let (mouse_x, mouse_y, click_x, click_y) = self.input.mouse_uniform();
+            uniforms::set_vec4(&self.uniforms, "u_mouse", mouse_x, mouse_y, click_x, click_y);
+            // u_keyboard is a `uniform uvec4 u_keyboard[3]` covering GLFW's full key
+            // code range; see `InputState::keyboard_bits`.
+            let keyboard_bits = self.input.keyboard_bits();
+            for (index, chunk) in keyboard_bits.chunks_exact(4).enumerate() {
+                uniforms::set_uvec4_at(
+                    &self.uniforms,
+                    "u_keyboard",
+                    index,
+                    chunk[0],
+                    chunk[1],
+                    chunk[2],
+                    chunk[3],
+                );
+            }
+
+            // Bind texture channels to units 0..MAX_CHANNELS and set their samplers
+            for (index, channel) in self.channels.iter().enumerate() {
+                gl::ActiveTexture(gl::TEXTURE0 + index as u32);
+                gl::BindTexture(gl::TEXTURE_2D, channel.texture);
+                uniforms::set_i32(&self.uniforms, &format!("u_channel{}", index), index as i32);
+                uniforms::set_vec2_at(
+                    &self.uniforms,
+                    "u_channelResolution",
+                    index,
+                    channel.width as f32,
+                    channel.height as f32,
+                );
+            }
+
+            // The final on-screen pass may read every feedback buffer's result from
+            // this frame (`self.passes.len()` signals "no later pass to exclude").
+            passes::bind_buffer_samplers(&self.passes, self.passes.len(), &self.uniforms);
 
             // Draw the quad
             gl::BindVertexArray(self.vao);
             gl::DrawArrays(gl::TRIANGLES, 0, 6);
             gl::BindVertexArray(0);
-        }
-
-        // Swap front and back buffers - Blocking since we have vsync enabled
-        self.window.swap_buffers();
-    }
-
-    // Processes window events and checks if the window should close
-    pub fn process_events(&mut self) -> bool {
-        self.glfw.poll_events(); // Poll for events
 
-        let mut should_close = false;
-        for (_, event) in glfw::flush_messages(&self.events) {
-            match event {
-                glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
-                    self.window.set_should_close(true);
-                    should_close = true;
-                }
-                glfw::WindowEvent::FramebufferSize(width, height) => {
-                    // Update the viewport
-                    unsafe {
-                        gl::Viewport(0, 0, width, height);
-                    }
-                }
-                _ => {}
+            // Advance and draw the particle system, if the compute pipeline is present
+            if let (Some(compute_program), Some(particle_program), Some(particles)) =
+                (self.compute_program, self.particle_program, self.particles.as_ref())
+            {
+                gl::UseProgram(compute_program);
+                uniforms::set_f32(&self.compute_uniforms, "u_time", self.time_state.total_time);
+                uniforms::set_f32(&self.compute_uniforms, "u_deltaTime", self.time_state.delta_time);
+                particles.dispatch(compute_program);
+                particles.draw(particle_program);
             }
         }
-
-        if self.window.should_close() {
-            should_close = true;
-        }
-
-        !should_close
     }
 }
 
@@ -195,6 +328,59 @@ impl Drop for ShaderContext {
             gl::DeleteProgram(self.shader_program);
             gl::DeleteBuffers(1, &self.vbo);
             gl::DeleteVertexArrays(1, &self.vao);
+            for channel in &self.channels {
+                gl::DeleteTextures(1, &channel.texture);
+            }
+            if let Some(compute_program) = self.compute_program {
+                gl::DeleteProgram(compute_program);
+            }
+            if let Some(particle_program) = self.particle_program {
+                gl::DeleteProgram(particle_program);
+            }
+        }
+    }
+}
+
+// Builds the optional compute/particle pipeline if `compute.glsl`, `particle_vertex.glsl`,
+// and `particle_fragment.glsl` are all present in `shader_dir`; otherwise leaves it disabled.
+fn load_particle_pipeline(
+    shader_dir: &Path,
+) -> (Option<u32>, UniformMap, Option<u32>, Option<ParticleSystem>) {
+    let compute_path = shaders::optional_stage_path(shader_dir, "compute.glsl");
+    let particle_vertex_path = shaders::optional_stage_path(shader_dir, "particle_vertex.glsl");
+    let particle_fragment_path = shaders::optional_stage_path(shader_dir, "particle_fragment.glsl");
+
+    let (compute_path, particle_vertex_path, particle_fragment_path) =
+        match (compute_path, particle_vertex_path, particle_fragment_path) {
+            (Some(c), Some(v), Some(f)) => (c, v, f),
+            _ => return (None, UniformMap::new(), None, None),
+        };
+
+    match (
+        compile_compute_program(&compute_path),
+        compile_shader_program(&ShaderStagePaths::new(particle_vertex_path, particle_fragment_path)),
+    ) {
+        (Ok(compute_program), Ok(particle_program)) => {
+            let compute_uniforms = uniforms::reflect_uniforms(compute_program);
+            (
+                Some(compute_program),
+                compute_uniforms,
+                Some(particle_program),
+                Some(ParticleSystem::new(PARTICLE_COUNT)),
+            )
+        }
+        (compute_result, particle_result) => {
+            // Only one side may have compiled; delete it so the orphaned program
+            // object isn't leaked, since the pipeline as a whole stays disabled.
+            match compute_result {
+                Ok(compute_program) => unsafe { gl::DeleteProgram(compute_program) },
+                Err(e) => eprintln!("Failed to compile compute shader: {}", e),
+            }
+            match particle_result {
+                Ok(particle_program) => unsafe { gl::DeleteProgram(particle_program) },
+                Err(e) => eprintln!("Failed to compile particle shaders: {}", e),
+            }
+            (None, UniformMap::new(), None, None)
         }
     }
 }
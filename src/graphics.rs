@@ -0,0 +1,9 @@
+pub mod buffers;
+pub mod input;
+pub mod offscreen;
+pub mod particles;
+pub mod passes;
+pub mod shaders;
+pub mod textures;
+pub mod time;
+pub mod uniforms;
@@ -0,0 +1,75 @@
+use std::collections::HashSet;
+
+use glfw::{Action, Key, MouseButton, WindowEvent};
+
+// GLFW_KEY_LAST (GLFW_KEY_MENU) is 348; round up to a whole number of uvec4s (groups
+// of 4 u32s) so it maps cleanly onto a `uniform uvec4 u_keyboard[3]` array.
+const KEYBOARD_BITS_LEN: usize = 12;
+
+// Tracks cursor position, button state, and held keys so they can be surfaced to
+// shaders as the `u_mouse` and `u_keyboard` uniforms.
+pub struct InputState {
+    mouse_pos: (f32, f32),
+    last_click_pos: (f32, f32),
+    mouse_down: bool,
+    pressed_keys: HashSet<Key>,
+}
+
+impl InputState {
+    pub fn new() -> Self {
+        InputState {
+            mouse_pos: (0.0, 0.0),
+            last_click_pos: (0.0, 0.0),
+            mouse_down: false,
+            pressed_keys: HashSet::new(),
+        }
+    }
+
+    // Updates tracked state from a single window event.
+    pub fn process_event(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::CursorPos(x, y) => {
+                self.mouse_pos = (*x as f32, *y as f32);
+            }
+            WindowEvent::MouseButton(MouseButton::Button1, Action::Press, _) => {
+                self.mouse_down = true;
+                self.last_click_pos = self.mouse_pos;
+            }
+            WindowEvent::MouseButton(MouseButton::Button1, Action::Release, _) => {
+                self.mouse_down = false;
+            }
+            WindowEvent::Key(key, _, Action::Press, _) => {
+                self.pressed_keys.insert(*key);
+            }
+            WindowEvent::Key(key, _, Action::Release, _) => {
+                self.pressed_keys.remove(key);
+            }
+            _ => {}
+        }
+    }
+
+    // Shadertoy convention: xy = current cursor position, zw = last click position,
+    // with the sign of z carrying whether the button is currently held.
+    pub fn mouse_uniform(&self) -> (f32, f32, f32, f32) {
+        let (x, y) = self.mouse_pos;
+        let (click_x, click_y) = self.last_click_pos;
+        let held_sign = if self.mouse_down { 1.0 } else { -1.0 };
+        (x, y, click_x * held_sign, click_y)
+    }
+
+    // Packs the held keys' GLFW key codes into a bitset spanning GLFW's full code range
+    // (printable keys 0..~96, plus the non-printable block 256..=GLFW_KEY_LAST == 348),
+    // split across `KEYBOARD_BITS_LEN` u32s, so a shader can test
+    // `(u_keyboard[code / 32] >> (code % 32)) & 1u`.
+    pub fn keyboard_bits(&self) -> [u32; KEYBOARD_BITS_LEN] {
+        let mut bits = [0u32; KEYBOARD_BITS_LEN];
+        for key in &self.pressed_keys {
+            let code = *key as i32;
+            if (0..(KEYBOARD_BITS_LEN as i32 * 32)).contains(&code) {
+                let code = code as u32;
+                bits[(code / 32) as usize] |= 1 << (code % 32);
+            }
+        }
+        bits
+    }
+}
@@ -0,0 +1,97 @@
+// Framebuffer object a frame can be rendered into instead of the default (window)
+// framebuffer, so a frame can be captured at an arbitrary resolution independent of
+// the window size — used by the headless frame-export mode.
+pub struct OffscreenTarget {
+    pub fbo: u32,
+    pub color_texture: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl OffscreenTarget {
+    pub fn new(width: u32, height: u32) -> Self {
+        unsafe {
+            let mut color_texture = 0;
+            gl::GenTextures(1, &mut color_texture);
+            gl::BindTexture(gl::TEXTURE_2D, color_texture);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::RGBA8 as i32,
+                width as i32,
+                height as i32,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                std::ptr::null(),
+            );
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+
+            let mut fbo = 0;
+            gl::GenFramebuffers(1, &mut fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::TEXTURE_2D,
+                color_texture,
+                0,
+            );
+
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            if status != gl::FRAMEBUFFER_COMPLETE {
+                eprintln!("Offscreen framebuffer incomplete: 0x{:X}", status);
+            }
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+
+            OffscreenTarget {
+                fbo,
+                color_texture,
+                width,
+                height,
+            }
+        }
+    }
+
+    // Reads the color attachment back as top-to-bottom RGBA8 rows (glReadPixels returns
+    // bottom-to-top, matching OpenGL's texture coordinate convention, so the rows are
+    // flipped to match the row order `image::save_buffer` expects).
+    pub fn read_pixels(&self) -> Vec<u8> {
+        let row_bytes = self.width as usize * 4;
+        let mut bottom_up = vec![0u8; row_bytes * self.height as usize];
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::ReadPixels(
+                0,
+                0,
+                self.width as i32,
+                self.height as i32,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                bottom_up.as_mut_ptr() as *mut _,
+            );
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+
+        let mut top_down = vec![0u8; bottom_up.len()];
+        for row in 0..self.height as usize {
+            let src = row * row_bytes;
+            let dst = (self.height as usize - 1 - row) * row_bytes;
+            top_down[dst..dst + row_bytes].copy_from_slice(&bottom_up[src..src + row_bytes]);
+        }
+        top_down
+    }
+}
+
+impl Drop for OffscreenTarget {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.fbo);
+            gl::DeleteTextures(1, &self.color_texture);
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use std::mem;
+
+/// Binding point shared by the compute dispatch and the point-rendering draw call.
+pub const PARTICLE_BINDING: u32 = 7;
+
+const WORK_GROUP_SIZE: u32 = 64;
+
+#[repr(C)]
+struct Particle {
+    pos: [f32; 4],
+}
+
+// Owns the SSBO backing a compute-driven particle system. The same buffer is bound
+// at `PARTICLE_BINDING` for both the compute dispatch and the subsequent point draw,
+// so the vertex shader can read `par[gl_VertexID].pos` straight out of it.
+pub struct ParticleSystem {
+    pub ssbo: u32,
+    pub count: u32,
+    vao: u32,
+}
+
+impl ParticleSystem {
+    // Allocates the SSBO and seeds it with `count` particles spread around a ring,
+    // leaving the simulation itself to the compute shader.
+    pub fn new(count: u32) -> Self {
+        let particles: Vec<Particle> = (0..count)
+            .map(|i| {
+                let angle = (i as f32 / count as f32) * std::f32::consts::TAU;
+                Particle {
+                    pos: [angle.cos(), angle.sin(), 0.0, 0.0],
+                }
+            })
+            .collect();
+
+        let ssbo = unsafe {
+            let mut ssbo = 0;
+            gl::GenBuffers(1, &mut ssbo);
+            gl::BindBuffer(gl::SHADER_STORAGE_BUFFER, ssbo);
+            gl::BufferData(
+                gl::SHADER_STORAGE_BUFFER,
+                (particles.len() * mem::size_of::<Particle>()) as isize,
+                particles.as_ptr() as *const _,
+                gl::DYNAMIC_DRAW,
+            );
+            gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, PARTICLE_BINDING, ssbo);
+            gl::BindBuffer(gl::SHADER_STORAGE_BUFFER, 0);
+            ssbo
+        };
+
+        // The point draw has no vertex attributes of its own (the vertex shader pulls
+        // straight from the SSBO via gl_VertexID), but core profile still requires a
+        // VAO to be bound for any draw call.
+        let vao = unsafe {
+            let mut vao = 0;
+            gl::GenVertexArrays(1, &mut vao);
+            vao
+        };
+
+        ParticleSystem { ssbo, count, vao }
+    }
+
+    // Advances the simulation by dispatching `compute_program` over the particle buffer.
+    pub fn dispatch(&self, compute_program: u32) {
+        unsafe {
+            gl::UseProgram(compute_program);
+            gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, PARTICLE_BINDING, self.ssbo);
+            let groups = self.count.div_ceil(WORK_GROUP_SIZE);
+            gl::DispatchCompute(groups, 1, 1);
+            gl::MemoryBarrier(gl::SHADER_STORAGE_BARRIER_BIT);
+        }
+    }
+
+    // Draws the particle buffer as points using `particle_program`.
+    pub fn draw(&self, particle_program: u32) {
+        unsafe {
+            gl::UseProgram(particle_program);
+            gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, PARTICLE_BINDING, self.ssbo);
+            gl::BindVertexArray(self.vao);
+            gl::DrawArrays(gl::POINTS, 0, self.count as i32);
+            gl::BindVertexArray(0);
+        }
+    }
+}
+
+impl Drop for ParticleSystem {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteBuffers(1, &self.ssbo);
+            gl::DeleteVertexArrays(1, &self.vao);
+        }
+    }
+}
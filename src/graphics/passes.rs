@@ -0,0 +1,278 @@
+use std::path::Path;
+
+use super::shaders::{self, compile_shader_program, ShaderStagePaths};
+use super::uniforms::{self, UniformMap};
+
+// Shadertoy-style named feedback buffers, discovered (in this order) alongside the
+// main vertex/fragment shader as "buffer_a.glsl" .. "buffer_d.glsl". Each present file
+// becomes a pass that renders into its own ping-pong buffer and is readable by name
+// (u_bufferA .. u_bufferD) from every later pass and from the final on-screen pass.
+const BUFFER_NAMES: [&str; 4] = ["buffer_a", "buffer_b", "buffer_c", "buffer_d"];
+const BUFFER_SAMPLER_NAMES: [&str; 4] = ["u_bufferA", "u_bufferB", "u_bufferC", "u_bufferD"];
+
+// Two color-texture FBOs for a single feedback buffer: one is sampled as the previous
+// frame's result while the other is rendered into, then they swap.
+struct PingPongBuffer {
+    textures: [u32; 2],
+    fbos: [u32; 2],
+    width: u32,
+    height: u32,
+    read_index: usize,
+}
+
+impl PingPongBuffer {
+    fn new(width: u32, height: u32) -> Self {
+        let mut textures = [0u32; 2];
+        let mut fbos = [0u32; 2];
+        unsafe {
+            gl::GenTextures(2, textures.as_mut_ptr());
+            gl::GenFramebuffers(2, fbos.as_mut_ptr());
+            for i in 0..2 {
+                Self::allocate(textures[i], fbos[i], width, height);
+            }
+        }
+        PingPongBuffer {
+            textures,
+            fbos,
+            width,
+            height,
+            read_index: 0,
+        }
+    }
+
+    unsafe fn allocate(texture: u32, fbo: u32, width: u32, height: u32) {
+        gl::BindTexture(gl::TEXTURE_2D, texture);
+        gl::TexImage2D(
+            gl::TEXTURE_2D,
+            0,
+            gl::RGBA32F as i32,
+            width as i32,
+            height as i32,
+            0,
+            gl::RGBA,
+            gl::FLOAT,
+            std::ptr::null(),
+        );
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+
+        gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+        gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, texture, 0);
+
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        gl::BindTexture(gl::TEXTURE_2D, 0);
+    }
+
+    // Reallocates both textures at the new resolution; previous contents are lost,
+    // same as a Shadertoy buffer resizing with the browser window.
+    fn resize(&mut self, width: u32, height: u32) {
+        if self.width == width && self.height == height {
+            return;
+        }
+        unsafe {
+            for i in 0..2 {
+                Self::allocate(self.textures[i], self.fbos[i], width, height);
+            }
+        }
+        self.width = width;
+        self.height = height;
+    }
+
+    fn read_texture(&self) -> u32 {
+        self.textures[self.read_index]
+    }
+
+    fn write_fbo(&self) -> u32 {
+        self.fbos[1 - self.read_index]
+    }
+
+    fn swap(&mut self) {
+        self.read_index = 1 - self.read_index;
+    }
+}
+
+impl Drop for PingPongBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(2, self.fbos.as_ptr());
+            gl::DeleteTextures(2, self.textures.as_ptr());
+        }
+    }
+}
+
+// A single named feedback pass: its own fragment shader (sharing the main vertex
+// shader), cached uniform locations, and the ping-pong buffer it renders into.
+pub struct Pass {
+    pub name: &'static str,
+    pub sampler_name: &'static str,
+    program: u32,
+    uniforms: UniformMap,
+    buffer: PingPongBuffer,
+}
+
+impl Pass {
+    fn compile(vertex_path: &str, fragment_path: &str) -> Result<(u32, UniformMap), shaders::ShaderError> {
+        let program =
+            compile_shader_program(&ShaderStagePaths::new(vertex_path.to_string(), fragment_path.to_string()))?;
+        let uniforms = uniforms::reflect_uniforms(program);
+        Ok((program, uniforms))
+    }
+
+    // The buffer's current resolution, so callers re-discovering passes after a shader
+    // reload can keep sizing new buffers consistently with the ones already running.
+    pub fn resolution(&self) -> (u32, u32) {
+        (self.buffer.width, self.buffer.height)
+    }
+}
+
+impl Drop for Pass {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.program);
+        }
+    }
+}
+
+// Discovers and compiles whichever of `buffer_a.glsl` .. `buffer_d.glsl` exist next to
+// the vertex shader, in that fixed order (later buffers can read earlier ones). Returns
+// the passes that compiled successfully; a pass whose shader fails to compile is logged
+// and skipped rather than aborting the rest of the pipeline.
+pub fn discover_passes(shader_dir: &Path, vertex_path: &str, width: u32, height: u32) -> Vec<Pass> {
+    let mut passes = Vec::new();
+
+    for (name, sampler_name) in BUFFER_NAMES.iter().zip(BUFFER_SAMPLER_NAMES.iter()) {
+        let fragment_path = match shaders::optional_stage_path(shader_dir, &format!("{}.glsl", name)) {
+            Some(path) => path,
+            None => continue,
+        };
+
+        match Pass::compile(vertex_path, &fragment_path) {
+            Ok((program, uniforms)) => passes.push(Pass {
+                name,
+                sampler_name,
+                program,
+                uniforms,
+                buffer: PingPongBuffer::new(width, height),
+            }),
+            Err(e) => eprintln!("Failed to compile feedback pass `{}`: {}", name, e),
+        }
+    }
+
+    passes
+}
+
+// Re-discovers and recompiles passes, keeping each pass's currently running program
+// (and its accumulated buffer contents) if its shader fails to recompile.
+pub fn reload_passes(passes: &mut Vec<Pass>, shader_dir: &Path, vertex_path: &str, width: u32, height: u32) {
+    for (name, sampler_name) in BUFFER_NAMES.iter().zip(BUFFER_SAMPLER_NAMES.iter()) {
+        let fragment_path = match shaders::optional_stage_path(shader_dir, &format!("{}.glsl", name)) {
+            Some(path) => path,
+            None => continue,
+        };
+
+        match passes.iter_mut().find(|p| p.name == *name) {
+            Some(pass) => match Pass::compile(vertex_path, &fragment_path) {
+                Ok((new_program, new_uniforms)) => {
+                    unsafe {
+                        gl::DeleteProgram(pass.program);
+                    }
+                    pass.program = new_program;
+                    pass.uniforms = new_uniforms;
+                }
+                Err(e) => eprintln!("Failed to reload feedback pass `{}`: {}", name, e),
+            },
+            None => match Pass::compile(vertex_path, &fragment_path) {
+                Ok((program, uniforms)) => {
+                    // Keep `passes` ordered by BUFFER_NAMES position, not insertion order,
+                    // since render_passes/bind_buffer_samplers treat vec position as both
+                    // render order and the "already rendered this frame" cutoff.
+                    let target_order = buffer_order(name);
+                    let insert_at = passes
+                        .iter()
+                        .position(|p| buffer_order(p.name) > target_order)
+                        .unwrap_or(passes.len());
+                    passes.insert(
+                        insert_at,
+                        Pass {
+                            name,
+                            sampler_name,
+                            program,
+                            uniforms,
+                            buffer: PingPongBuffer::new(width, height),
+                        },
+                    );
+                }
+                Err(e) => eprintln!("Failed to compile new feedback pass `{}`: {}", name, e),
+            },
+        }
+    }
+}
+
+// A pass's fixed a→b→c→d position, used to keep `passes` sorted by BUFFER_NAMES order
+// regardless of the order buffers are discovered or added in.
+fn buffer_order(name: &str) -> usize {
+    BUFFER_NAMES.iter().position(|b| *b == name).unwrap_or(usize::MAX)
+}
+
+// Renders every pass in order into its own buffer, binding each earlier pass's result
+// from this frame and the pass's own previous frame (by name) as sampler uniforms; after
+// the loop every buffer holds this frame's result for the final on-screen pass. `bind_common_uniforms` sets whatever per-frame
+// uniforms (u_time, u_resolution, channels, ...) the caller wants every pass to see.
+pub fn render_passes(
+    passes: &mut [Pass],
+    vao: u32,
+    width: u32,
+    height: u32,
+    bind_common_uniforms: impl Fn(&UniformMap),
+) {
+    for i in 0..passes.len() {
+        passes[i].buffer.resize(width, height);
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, passes[i].buffer.write_fbo());
+            gl::Viewport(0, 0, width as i32, height as i32);
+            gl::UseProgram(passes[i].program);
+        }
+
+        bind_common_uniforms(&passes[i].uniforms);
+        bind_buffer_samplers(passes, i, &passes[i].uniforms);
+
+        unsafe {
+            gl::BindVertexArray(vao);
+            gl::DrawArrays(gl::TRIANGLES, 0, 6);
+            gl::BindVertexArray(0);
+        }
+
+        passes[i].buffer.swap();
+    }
+
+    unsafe {
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+    }
+}
+
+// Binds the current read texture of every pass with index <= reading_pass (every pass
+// when called for the final screen pass) to texture unit `first_unit + index`, and sets
+// that buffer's sampler uniform if the shader declares it. For passes already rendered
+// this frame that texture holds this frame's result; for `reading_pass` itself it holds
+// its own previous frame.
+pub fn bind_buffer_samplers(passes: &[Pass], reading_pass: usize, uniforms: &UniformMap) {
+    let first_unit = super::textures::MAX_CHANNELS as u32;
+    for (slot, pass) in passes.iter().enumerate() {
+        if reading_pass != passes.len() && slot > reading_pass {
+            // A pass may read its own previous frame (feedback) and any earlier buffer's
+            // frame from the same frame, but not a buffer that hasn't rendered yet this
+            // frame (matching Shadertoy's ordering rule, which avoids needing
+            // synchronization within a single frame). The final on-screen pass (signaled
+            // by `reading_pass == passes.len()`) may read every buffer.
+            continue;
+        }
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + first_unit + slot as u32);
+            gl::BindTexture(gl::TEXTURE_2D, pass.buffer.read_texture());
+        }
+        uniforms::set_i32(uniforms, pass.sampler_name, (first_unit + slot as u32) as i32);
+    }
+}
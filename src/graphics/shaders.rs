@@ -1,15 +1,193 @@
 use std::ffi::CString;
+use std::fmt;
 use std::fs;
 use std::path::Path;
 use std::ptr;
 
-// Compiles a shader from source code
-pub fn compile_shader(source: &str, shader_type: u32) -> Result<u32, String> {
+/// The shader stage a `ShaderError` or compile/link step refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShaderStage {
+    Vertex,
+    Fragment,
+    Geometry,
+    TessControl,
+    TessEvaluation,
+    Compute,
+}
+
+impl fmt::Display for ShaderStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShaderStage::Vertex => write!(f, "vertex"),
+            ShaderStage::Fragment => write!(f, "fragment"),
+            ShaderStage::Geometry => write!(f, "geometry"),
+            ShaderStage::TessControl => write!(f, "tessellation control"),
+            ShaderStage::TessEvaluation => write!(f, "tessellation evaluation"),
+            ShaderStage::Compute => write!(f, "compute"),
+        }
+    }
+}
+
+/// Paths to the shader stages that make up a program. `vertex` and `fragment`
+/// are always required; the remaining stages are only attached when present.
+pub struct ShaderStagePaths {
+    pub vertex: String,
+    pub fragment: String,
+    pub geometry: Option<String>,
+    pub tess_control: Option<String>,
+    pub tess_evaluation: Option<String>,
+}
+
+impl ShaderStagePaths {
+    // Builds a plain vertex/fragment pair with no optional stages attached.
+    pub fn new(vertex: String, fragment: String) -> Self {
+        ShaderStagePaths {
+            vertex,
+            fragment,
+            geometry: None,
+            tess_control: None,
+            tess_evaluation: None,
+        }
+    }
+
+    // Builds the required vertex/fragment pair plus whichever optional stage
+    // files (geometry.glsl, tess_control.glsl, tess_evaluation.glsl) exist
+    // alongside the vertex shader.
+    pub fn discover(vertex: String, fragment: String) -> Self {
+        let dir = Path::new(&vertex).parent().unwrap_or_else(|| Path::new("."));
+        let geometry = optional_stage_path(dir, "geometry.glsl");
+        let tess_control = optional_stage_path(dir, "tess_control.glsl");
+        let tess_evaluation = optional_stage_path(dir, "tess_evaluation.glsl");
+
+        ShaderStagePaths {
+            vertex,
+            fragment,
+            geometry,
+            tess_control,
+            tess_evaluation,
+        }
+    }
+}
+
+// Returns `dir/file_name` as a string if that file exists, so optional pipeline
+// stages can be wired in only when the caller has actually provided them.
+pub(crate) fn optional_stage_path(dir: &Path, file_name: &str) -> Option<String> {
+    let path = dir.join(file_name);
+    if path.exists() {
+        path.to_str().map(str::to_string)
+    } else {
+        None
+    }
+}
+
+/// Everything that can go wrong while compiling and linking a shader program.
+#[derive(Debug)]
+pub enum ShaderError {
+    /// The shader source file could not be read from disk.
+    ReadFailed { path: String, source: std::io::Error },
+    /// A single stage failed to compile; `log` is the raw GL info log.
+    CompileFailed {
+        stage: ShaderStage,
+        path: String,
+        log: String,
+    },
+    /// Linking the compiled stages into a program failed.
+    LinkFailed { log: String },
+    /// Shader source contained an interior NUL byte and couldn't become a `CString`.
+    BadCString,
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShaderError::ReadFailed { path, source } => {
+                write!(f, "failed to read shader source `{}`: {}", path, source)
+            }
+            ShaderError::CompileFailed { stage, path, log } => {
+                writeln!(f, "{} shader `{}` failed to compile:", stage, path)?;
+                for diagnostic in parse_info_log(log) {
+                    write!(f, "  {}", diagnostic)?;
+                }
+                Ok(())
+            }
+            ShaderError::LinkFailed { log } => write!(f, "program linking failed:\n{}", log),
+            ShaderError::BadCString => {
+                write!(f, "shader source contains an interior NUL byte")
+            }
+        }
+    }
+}
+
+/// A single line of a parsed GLSL compiler info log.
+pub struct Diagnostic {
+    pub line: Option<u32>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => writeln!(f, "line {}: {}", line, self.message),
+            None => writeln!(f, "{}", self.message),
+        }
+    }
+}
+
+// Parses driver-reported GLSL info logs into per-line diagnostics with the source line
+// number pulled out, where present. Covers the two info log formats seen in practice:
+// NVIDIA-style `"0(12) : error C1008: ..."` and Mesa/ANGLE-style `"ERROR: 0:12: ..."`.
+// Lines that don't match either shape are kept verbatim with no line number.
+fn parse_info_log(log: &str) -> Vec<Diagnostic> {
+    log.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let line_number = parse_nvidia_line(line).or_else(|| parse_mesa_line(line));
+            Diagnostic {
+                line: line_number,
+                message: line.trim().to_string(),
+            }
+        })
+        .collect()
+}
+
+// NVIDIA format: "0(12) : error C1008: undefined variable ..."
+fn parse_nvidia_line(line: &str) -> Option<u32> {
+    let after_paren_open = line.split_once('(')?.1;
+    let number = after_paren_open.split_once(')')?.0;
+    number.trim().parse().ok()
+}
+
+// Mesa/ANGLE format: "ERROR: 0:12: 'foo' : undeclared identifier"
+fn parse_mesa_line(line: &str) -> Option<u32> {
+    let mut fields = line.splitn(3, ':');
+    fields.next()?; // "ERROR" / "WARNING"
+    fields.next()?; // source string index, always 0 for a single-source shader
+    let rest = fields.next()?.trim_start();
+    rest.split_once(':')?.0.parse().ok()
+}
+
+impl std::error::Error for ShaderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ShaderError::ReadFailed { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+// Compiles a single shader stage from source. `path` is only used to label a
+// `CompileFailed` error with the file the offending source came from.
+pub fn compile_shader(
+    source: &str,
+    shader_type: u32,
+    stage: ShaderStage,
+    path: &str,
+) -> Result<u32, ShaderError> {
     unsafe {
         let shader = gl::CreateShader(shader_type); // Create a new shader object
 
         // Convert shader source to a C-compatible string
-        let c_source = CString::new(source.as_bytes()).unwrap();
+        let c_source = CString::new(source.as_bytes()).map_err(|_| ShaderError::BadCString)?;
         gl::ShaderSource(shader, 1, &c_source.as_ptr(), ptr::null());
         gl::CompileShader(shader); // Compile the shader
 
@@ -24,60 +202,76 @@ pub fn compile_shader(source: &str, shader_type: u32) -> Result<u32, String> {
             let mut buffer = vec![0u8; len as usize];
             gl::GetShaderInfoLog(shader, len, ptr::null_mut(), buffer.as_mut_ptr() as *mut i8);
 
-            let error = String::from_utf8_lossy(&buffer);
+            let log = String::from_utf8_lossy(&buffer).to_string();
 
             gl::DeleteShader(shader); // Clean up the shader object
-            return Err(error.to_string());
+            return Err(ShaderError::CompileFailed {
+                stage,
+                path: path.to_string(),
+                log,
+            });
         }
 
         Ok(shader) // Return the compiled shader ID
     }
 }
 
-// Compiles and links a shader program from vertex and fragment shader files
-pub fn compile_shader_program(vert_path: &str, frag_path: &str) -> u32 {
-    // Read shader sources from files
-    let vert_source = match read_shader_source(vert_path) {
-        Ok(source) => source,
-        Err(e) => {
-            eprintln!("Failed to read vertex shader: {}", e);
-            return 0;
-        }
-    };
+// Compiles a single stage from `path` and records its shader object in `compiled`
+fn compile_stage(
+    path: &str,
+    gl_type: u32,
+    stage: ShaderStage,
+    compiled: &mut Vec<u32>,
+) -> Result<(), ShaderError> {
+    let source = read_shader_source(path)?;
+    let shader = compile_shader(&source, gl_type, stage, path)?;
+    compiled.push(shader);
+    Ok(())
+}
 
-    let frag_source = match read_shader_source(frag_path) {
-        Ok(source) => source,
-        Err(e) => {
-            eprintln!("Failed to read fragment shader: {}", e);
-            return 0;
-        }
-    };
-
-    // Compile vertex and fragment shaders
-    let vert_shader = match compile_shader(&vert_source, gl::VERTEX_SHADER) {
-        Ok(shader) => shader,
-        Err(e) => {
-            eprintln!("Vertex shader compilation failed: {}", e);
-            return 0;
-        }
-    };
-
-    let frag_shader = match compile_shader(&frag_source, gl::FRAGMENT_SHADER) {
-        Ok(shader) => shader,
-        Err(e) => {
-            eprintln!("Fragment shader compilation failed: {}", e);
-            unsafe {
-                gl::DeleteShader(vert_shader);
+// Compiles whichever stages are present in `stages` into `compiled`
+fn compile_stages(stages: &ShaderStagePaths, compiled: &mut Vec<u32>) -> Result<(), ShaderError> {
+    compile_stage(&stages.vertex, gl::VERTEX_SHADER, ShaderStage::Vertex, compiled)?;
+    compile_stage(&stages.fragment, gl::FRAGMENT_SHADER, ShaderStage::Fragment, compiled)?;
+
+    if let Some(path) = &stages.geometry {
+        compile_stage(path, gl::GEOMETRY_SHADER, ShaderStage::Geometry, compiled)?;
+    }
+    if let Some(path) = &stages.tess_control {
+        compile_stage(path, gl::TESS_CONTROL_SHADER, ShaderStage::TessControl, compiled)?;
+    }
+    if let Some(path) = &stages.tess_evaluation {
+        compile_stage(
+            path,
+            gl::TESS_EVALUATION_SHADER,
+            ShaderStage::TessEvaluation,
+            compiled,
+        )?;
+    }
+
+    Ok(())
+}
+
+// Compiles and links a shader program from the given stage files. `stages.vertex` and
+// `stages.fragment` are always attached; any other stage is attached only when present.
+pub fn compile_shader_program(stages: &ShaderStagePaths) -> Result<u32, ShaderError> {
+    let mut compiled: Vec<u32> = Vec::new();
+
+    if let Err(e) = compile_stages(stages, &mut compiled) {
+        unsafe {
+            for shader in &compiled {
+                gl::DeleteShader(*shader);
             }
-            return 0;
         }
-    };
+        return Err(e);
+    }
 
     // Link shaders into a program
     unsafe {
         let program = gl::CreateProgram();
-        gl::AttachShader(program, vert_shader);
-        gl::AttachShader(program, frag_shader);
+        for shader in &compiled {
+            gl::AttachShader(program, *shader);
+        }
         gl::LinkProgram(program);
 
         // Check for linking errors
@@ -96,26 +290,74 @@ pub fn compile_shader_program(vert_path: &str, frag_path: &str) -> u32 {
                 buffer.as_mut_ptr() as *mut i8,
             );
 
-            let error = String::from_utf8_lossy(&buffer);
-            eprintln!("Program linking failed: {}", error);
+            let log = String::from_utf8_lossy(&buffer).to_string();
+
+            gl::DeleteProgram(program);
+            for shader in &compiled {
+                gl::DeleteShader(*shader);
+            }
+
+            return Err(ShaderError::LinkFailed { log });
+        }
+
+        // Detach and delete shaders now that they're linked into the program
+        for shader in &compiled {
+            gl::DetachShader(program, *shader);
+            gl::DeleteShader(*shader);
+        }
+
+        Ok(program) // Return the linked program ID
+    }
+}
+
+// Compiles and links a compute-only program from a single compute shader file.
+pub fn compile_compute_program(path: &str) -> Result<u32, ShaderError> {
+    let source = read_shader_source(path)?;
+    let shader = compile_shader(&source, gl::COMPUTE_SHADER, ShaderStage::Compute, path)?;
+
+    unsafe {
+        let program = gl::CreateProgram();
+        gl::AttachShader(program, shader);
+        gl::LinkProgram(program);
+
+        let mut success = 0;
+        gl::GetProgramiv(program, gl::LINK_STATUS, &mut success);
+
+        if success == 0 {
+            let mut len = 0;
+            gl::GetProgramiv(program, gl::INFO_LOG_LENGTH, &mut len);
+
+            let mut buffer = vec![0u8; len as usize];
+            gl::GetProgramInfoLog(
+                program,
+                len,
+                ptr::null_mut(),
+                buffer.as_mut_ptr() as *mut i8,
+            );
+
+            let log = String::from_utf8_lossy(&buffer).to_string();
 
             gl::DeleteProgram(program);
-            gl::DeleteShader(vert_shader);
-            gl::DeleteShader(frag_shader);
+            gl::DeleteShader(shader);
 
-            return 0;
+            return Err(ShaderError::LinkFailed { log });
         }
 
-        // Clean up shaders after linking
-        gl::DetachShader(program, vert_shader);
-        gl::DetachShader(program, frag_shader);
-        gl::DeleteShader(vert_shader);
-        gl::DeleteShader(frag_shader);
+        gl::DetachShader(program, shader);
+        gl::DeleteShader(shader);
 
-        program // Return the linked program ID
+        Ok(program)
     }
 }
 
+// The `gles` feature targets an ES 3.0 context (selected alongside the `egl` backend),
+// which requires "#version 300 es" and explicit precision qualifiers instead of desktop
+// GL's "#version 330 core".
+#[cfg(feature = "gles")]
+const GLSL_VERSION_PREFIX: &str = "#version 300 es\nprecision mediump float;\n";
+#[cfg(not(feature = "gles"))]
+const GLSL_VERSION_PREFIX: &str = "#version 330 core\n";
+
 // Creates default shaders if they do not exist
 pub fn create_default_shaders(vert_path: &str, frag_path: &str) {
     // Create shaders directory if it doesn't exist
@@ -123,8 +365,10 @@ pub fn create_default_shaders(vert_path: &str, frag_path: &str) {
 
     // Default vertex shader
     if !Path::new(vert_path).exists() {
-        let default_vertex = r#"#version 330 core
-layout (location = 0) in vec3 aPos;
+        let default_vertex = format!(
+            "{}{}",
+            GLSL_VERSION_PREFIX,
+            r#"layout (location = 0) in vec3 aPos;
 layout (location = 1) in vec2 aTexCoord;
 
 out vec2 TexCoord;
@@ -133,14 +377,17 @@ void main() {
   gl_Position = vec4(aPos, 1.0);
   TexCoord = aTexCoord;
 }
-"#;
+"#
+        );
         fs::write(vert_path, default_vertex).expect("Failed to write default vertex shader");
     }
 
     // Default fragment shader
     if !Path::new(frag_path).exists() {
-        let default_fragment = r#"#version 330 core
-in vec2 TexCoord;
+        let default_fragment = format!(
+            "{}{}",
+            GLSL_VERSION_PREFIX,
+            r#"in vec2 TexCoord;
 out vec4 FragColor;
 
 uniform float u_time;       // Total elapsed time (seconds)
@@ -151,19 +398,23 @@ uniform vec2 u_resolution;  // Window size (pixels)
 void main() {
   // Normalized coordinates (0 to 1)
   vec2 uv = TexCoord;
-  
+
   // Time varying color
   vec3 col = 0.5 + 0.5 * cos(u_time + uv.xyx + vec3(0.0, 2.0, 4.0));
-  
+
   // Output to screen
   FragColor = vec4(col, 1.0);
 }
-"#;
+"#
+        );
         fs::write(frag_path, default_fragment).expect("Failed to write default fragment shader");
     }
 }
 
 // Reads shader source code from a file
-fn read_shader_source(path: &str) -> Result<String, std::io::Error> {
-    fs::read_to_string(path)
+fn read_shader_source(path: &str) -> Result<String, ShaderError> {
+    fs::read_to_string(path).map_err(|source| ShaderError::ReadFailed {
+        path: path.to_string(),
+        source,
+    })
 }
@@ -0,0 +1,114 @@
+use std::path::Path;
+
+use image::GenericImageView;
+
+/// Shadertoy exposes up to four sampler channels (`u_channel0..u_channel3`).
+pub const MAX_CHANNELS: usize = 4;
+
+/// A single image channel uploaded to a `GL_TEXTURE_2D` with mipmaps.
+pub struct Channel {
+    pub texture: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+// Decodes `path` and uploads it as an RGBA8 GL_TEXTURE_2D with mipmaps, flipping rows
+// vertically because GL puts the first uploaded row at t = 0, the bottom of the quad's
+// texcoords, so the image's top row ends up at the top. Returns `None`
+// (after logging) if the file is missing or fails to decode, so one bad channel doesn't
+// abort startup.
+pub fn load_channel(path: &str) -> Option<Channel> {
+    let dynamic_image = decode_image(path)?;
+    let rgba = dynamic_image.flipv().to_rgba8();
+    let (width, height) = rgba.dimensions();
+
+    let texture = unsafe {
+        let mut texture = 0;
+        gl::GenTextures(1, &mut texture);
+        gl::BindTexture(gl::TEXTURE_2D, texture);
+
+        gl::TexImage2D(
+            gl::TEXTURE_2D,
+            0,
+            gl::RGBA8 as i32,
+            width as i32,
+            height as i32,
+            0,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            rgba.as_raw().as_ptr() as *const _,
+        );
+        gl::GenerateMipmap(gl::TEXTURE_2D);
+
+        gl::TexParameteri(
+            gl::TEXTURE_2D,
+            gl::TEXTURE_MIN_FILTER,
+            gl::LINEAR_MIPMAP_LINEAR as i32,
+        );
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as i32);
+
+        gl::BindTexture(gl::TEXTURE_2D, 0);
+        texture
+    };
+
+    Some(Channel {
+        texture,
+        width,
+        height,
+    })
+}
+
+fn decode_image(path: &str) -> Option<image::DynamicImage> {
+    let is_jxl = Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.eq_ignore_ascii_case("jxl"))
+        .unwrap_or(false);
+
+    if is_jxl {
+        match decode_jxl(path) {
+            Ok(image) => Some(image),
+            Err(e) => {
+                eprintln!("Failed to decode channel `{}`: {}", path, e);
+                None
+            }
+        }
+    } else {
+        match image::open(path) {
+            Ok(image) => Some(image),
+            Err(e) => {
+                eprintln!("Failed to decode channel `{}`: {}", path, e);
+                None
+            }
+        }
+    }
+}
+
+// Decodes a JPEG-XL file via jxl-oxide into an image::DynamicImage so it can go
+// through the same upload path as every other format.
+fn decode_jxl(path: &str) -> Result<image::DynamicImage, String> {
+    let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+    let jxl_image = jxl_oxide::JxlImage::builder()
+        .read(bytes.as_slice())
+        .map_err(|e| e.to_string())?;
+    let render = jxl_image.render_frame(0).map_err(|e| e.to_string())?;
+    let fb = render.image_all_channels();
+
+    let width = jxl_image.width();
+    let height = jxl_image.height();
+    let samples = fb.buf();
+
+    let mut rgba = image::RgbaImage::new(width, height);
+    for (pixel, chunk) in rgba.pixels_mut().zip(samples.chunks_exact(fb.channels())) {
+        let to_u8 = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+        let r = to_u8(chunk[0]);
+        let g = to_u8(*chunk.get(1).unwrap_or(&chunk[0]));
+        let b = to_u8(*chunk.get(2).unwrap_or(&chunk[0]));
+        let a = to_u8(*chunk.get(3).unwrap_or(&1.0));
+        *pixel = image::Rgba([r, g, b, a]);
+    }
+
+    Ok(image::DynamicImage::ImageRgba8(rgba))
+}
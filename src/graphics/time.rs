@@ -33,4 +33,12 @@ impl TimeState {
             .as_secs_f32(); // Calculate total elapsed time
         self.last_frame_time = now; // Update the last frame time
     }
+
+    // Advances time by a fixed `delta_seconds` instead of sampling the wall clock, so a
+    // frame export run gets the same `delta_time`/`total_time` on every run regardless of
+    // how long each frame actually took to render.
+    pub fn step(&mut self, delta_seconds: f32) {
+        self.delta_time = delta_seconds;
+        self.total_time += delta_seconds;
+    }
 }
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::ffi::CString;
+
+/// Active uniform locations for a linked program, keyed by uniform name.
+pub type UniformMap = HashMap<String, i32>;
+
+// Enumerates the active uniforms of a linked program and resolves their locations once,
+// so callers can look them up by name instead of calling glGetUniformLocation every frame.
+pub fn reflect_uniforms(program: u32) -> UniformMap {
+    let mut uniforms = HashMap::new();
+
+    unsafe {
+        let mut count = 0;
+        gl::GetProgramiv(program, gl::ACTIVE_UNIFORMS, &mut count);
+
+        let mut name_buf = vec![0u8; 256];
+        for index in 0..count as u32 {
+            let mut length = 0;
+            let mut size = 0;
+            let mut gl_type = 0;
+            gl::GetActiveUniform(
+                program,
+                index,
+                name_buf.len() as i32,
+                &mut length,
+                &mut size,
+                &mut gl_type,
+                name_buf.as_mut_ptr() as *mut i8,
+            );
+
+            let mut name = String::from_utf8_lossy(&name_buf[..length as usize]).into_owned();
+            // Array uniforms report their base name with a "[0]" suffix
+            if let Some(stripped) = name.strip_suffix("[0]") {
+                name.truncate(stripped.len());
+            }
+            if name.starts_with("gl_") {
+                continue;
+            }
+
+            let c_name = match CString::new(name.as_bytes()) {
+                Ok(c_name) => c_name,
+                Err(_) => continue,
+            };
+            let location = gl::GetUniformLocation(program, c_name.as_ptr());
+            uniforms.insert(name, location);
+        }
+    }
+
+    uniforms
+}
+
+// Sets a float uniform if it is present in `uniforms`; no-ops otherwise.
+pub fn set_f32(uniforms: &UniformMap, name: &str, value: f32) {
+    if let Some(&location) = uniforms.get(name) {
+        if location != -1 {
+            unsafe {
+                gl::Uniform1f(location, value);
+            }
+        }
+    }
+}
+
+// Sets a vec2 uniform if it is present in `uniforms`; no-ops otherwise.
+pub fn set_vec2(uniforms: &UniformMap, name: &str, x: f32, y: f32) {
+    if let Some(&location) = uniforms.get(name) {
+        if location != -1 {
+            unsafe {
+                gl::Uniform2f(location, x, y);
+            }
+        }
+    }
+}
+
+// Sets a vec4 uniform if it is present in `uniforms`; no-ops otherwise.
+pub fn set_vec4(uniforms: &UniformMap, name: &str, x: f32, y: f32, z: f32, w: f32) {
+    if let Some(&location) = uniforms.get(name) {
+        if location != -1 {
+            unsafe {
+                gl::Uniform4f(location, x, y, z, w);
+            }
+        }
+    }
+}
+
+// Sets an int (e.g. sampler binding) uniform if it is present in `uniforms`; no-ops otherwise.
+pub fn set_i32(uniforms: &UniformMap, name: &str, value: i32) {
+    if let Some(&location) = uniforms.get(name) {
+        if location != -1 {
+            unsafe {
+                gl::Uniform1i(location, value);
+            }
+        }
+    }
+}
+
+// Sets the `index`-th element of a vec2 array uniform, relying on array elements
+// occupying consecutive locations after the base (as is the case for all GL
+// implementations this viewer targets).
+pub fn set_vec2_at(uniforms: &UniformMap, name: &str, index: usize, x: f32, y: f32) {
+    if let Some(&location) = uniforms.get(name) {
+        if location != -1 {
+            unsafe {
+                gl::Uniform2f(location + index as i32, x, y);
+            }
+        }
+    }
+}
+
+// Sets the `index`-th element of a uvec4 array uniform; see `set_vec2_at` for the
+// consecutive-location assumption.
+pub fn set_uvec4_at(uniforms: &UniformMap, name: &str, index: usize, x: u32, y: u32, z: u32, w: u32) {
+    if let Some(&location) = uniforms.get(name) {
+        if location != -1 {
+            unsafe {
+                gl::Uniform4ui(location + index as i32, x, y, z, w);
+            }
+        }
+    }
+}
@@ -0,0 +1,16 @@
+use std::fs;
+use std::path::Path;
+
+// Writes one top-to-bottom RGBA8 frame as a numbered PNG (e.g. "frame_00042.png")
+// inside `output_dir`, creating the directory if it doesn't exist yet.
+pub fn write_frame_png(
+    output_dir: &str,
+    frame_index: u32,
+    width: u32,
+    height: u32,
+    rgba: &[u8],
+) -> image::ImageResult<()> {
+    fs::create_dir_all(output_dir).map_err(image::ImageError::IoError)?;
+    let path = Path::new(output_dir).join(format!("frame_{:05}.png", frame_index));
+    image::save_buffer(path, rgba, width, height, image::ColorType::Rgba8)
+}
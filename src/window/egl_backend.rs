@@ -0,0 +1,129 @@
+use khronos_egl as egl;
+
+use super::{ControlFlow, WindowError};
+
+// Offscreen EGL + OpenGL ES context, selected instead of `GlfwWindow` by the `egl`
+// feature. Targets headless/embedded use (CI, Raspberry-Pi-class devices, Wayland-only
+// sessions without an X server) where a desktop GLFW window isn't available. Renders
+// into a pbuffer surface rather than a window, since there is nothing to present to.
+pub struct EglWindow {
+    egl: egl::Instance<egl::Static>,
+    display: egl::Display,
+    context: egl::Context,
+    surface: egl::Surface,
+    width: u32,
+    height: u32,
+}
+
+impl EglWindow {
+    // `title` is accepted for parity with `GlfwWindow::create` but unused: there is no
+    // window to title in the offscreen/surfaceless case.
+    pub fn create(
+        width: u32,
+        height: u32,
+        _title: &str,
+        context_version: (u32, u32),
+    ) -> Result<Self, WindowError> {
+        let egl = egl::Instance::new(egl::Static);
+
+        let display = unsafe { egl.get_display(egl::DEFAULT_DISPLAY) }
+            .ok_or_else(|| WindowError::EglError("no default EGL display".to_string()))?;
+        egl.initialize(display)
+            .map_err(|e| WindowError::EglError(e.to_string()))?;
+
+        let config_attribs = [
+            egl::SURFACE_TYPE,
+            egl::PBUFFER_BIT,
+            egl::RENDERABLE_TYPE,
+            egl::OPENGL_ES3_BIT,
+            egl::RED_SIZE,
+            8,
+            egl::GREEN_SIZE,
+            8,
+            egl::BLUE_SIZE,
+            8,
+            egl::ALPHA_SIZE,
+            8,
+            egl::NONE,
+        ];
+        let config = egl
+            .choose_first_config(display, &config_attribs)
+            .map_err(|e| WindowError::EglError(e.to_string()))?
+            .ok_or(WindowError::CreationFailed)?;
+
+        let pbuffer_attribs = [egl::WIDTH, width as i32, egl::HEIGHT, height as i32, egl::NONE];
+        let surface = egl
+            .create_pbuffer_surface(display, config, &pbuffer_attribs)
+            .map_err(|e| WindowError::EglError(e.to_string()))?;
+
+        egl.bind_api(egl::OPENGL_ES_API)
+            .map_err(|e| WindowError::EglError(e.to_string()))?;
+
+        let (major, _minor) = context_version;
+        let context_attribs = [egl::CONTEXT_CLIENT_VERSION, major as i32, egl::NONE];
+        let context = egl
+            .create_context(display, config, None, &context_attribs)
+            .map_err(|e| WindowError::EglError(e.to_string()))?;
+
+        egl.make_current(display, Some(surface), Some(surface), Some(context))
+            .map_err(|e| WindowError::EglError(e.to_string()))?;
+
+        gl::load_with(|s| egl.get_proc_address(s).map_or(std::ptr::null(), |p| p as *const _));
+
+        Ok(EglWindow {
+            egl,
+            display,
+            context,
+            surface,
+            width,
+            height,
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    // There is no windowing system driving this loop, so unlike `GlfwWindow::run` there
+    // are no input events to hand to `callback` and no `should_close` to observe; the
+    // caller decides when to stop by returning `ControlFlow::Exit`.
+    pub fn run<F>(mut self, mut callback: F) -> Result<(), WindowError>
+    where
+        F: FnMut(&mut EglWindow) -> ControlFlow,
+    {
+        loop {
+            if let ControlFlow::Exit = callback(&mut self) {
+                break;
+            }
+
+            self.swap_buffers()?;
+        }
+
+        Ok(())
+    }
+
+    fn swap_buffers(&mut self) -> Result<(), WindowError> {
+        self.egl
+            .swap_buffers(self.display, self.surface)
+            .map_err(|e| WindowError::EglError(e.to_string()))?;
+
+        let error = unsafe { gl::GetError() };
+        if error != gl::NO_ERROR {
+            return Err(WindowError::ContextError(error));
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for EglWindow {
+    fn drop(&mut self) {
+        let _ = self.egl.destroy_surface(self.display, self.surface);
+        let _ = self.egl.destroy_context(self.display, self.context);
+        let _ = self.egl.terminate(self.display);
+    }
+}
@@ -0,0 +1,95 @@
+use std::sync::mpsc::Receiver;
+
+use glfw::Context;
+
+use super::{ControlFlow, WindowError};
+
+// Owns the GLFW context, window, and event queue, and drives the event loop on
+// the caller's behalf so that render state (ShaderContext) doesn't need to know
+// anything about GLFW itself. The default backend; compiled out when the `egl`
+// feature selects `EglWindow` instead.
+pub struct GlfwWindow {
+    pub glfw: glfw::Glfw,
+    pub window: glfw::Window,
+    pub events: Receiver<(f64, glfw::WindowEvent)>,
+}
+
+impl GlfwWindow {
+    // Creates the GLFW context and a windowed core-profile OpenGL context at
+    // `context_version` (e.g. `(3, 3)`, or `(4, 3)` when the compute pipeline is
+    // in use), and loads the GL function pointers through it.
+    pub fn create(
+        width: u32,
+        height: u32,
+        title: &str,
+        context_version: (u32, u32),
+    ) -> Result<Self, WindowError> {
+        let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).map_err(WindowError::Init)?;
+
+        let (major, minor) = context_version;
+        glfw.window_hint(glfw::WindowHint::ContextVersion(major, minor));
+        glfw.window_hint(glfw::WindowHint::OpenGlProfile(
+            glfw::OpenGlProfileHint::Core,
+        ));
+        glfw.window_hint(glfw::WindowHint::DoubleBuffer(true));
+
+        #[cfg(target_os = "macos")]
+        glfw.window_hint(glfw::WindowHint::OpenGlForwardCompat(true));
+
+        let (mut window, events) = glfw
+            .create_window(width, height, title, glfw::WindowMode::Windowed)
+            .ok_or(WindowError::CreationFailed)?;
+
+        window.make_current();
+        glfw.set_swap_interval(glfw::SwapInterval::Sync(1));
+        window.set_key_polling(true);
+        window.set_framebuffer_size_polling(true);
+        window.set_cursor_pos_polling(true);
+        window.set_mouse_button_polling(true);
+
+        gl::load_with(|s| window.get_proc_address(s) as *const _);
+
+        Ok(GlfwWindow {
+            glfw,
+            window,
+            events,
+        })
+    }
+
+    // Drives the event loop: polls GLFW, hands the frame's events to `callback` along
+    // with `self` (so the callback can render and react to input), then swaps buffers.
+    // Stops when the callback returns `ControlFlow::Exit`, the window is closed, or a
+    // buffer swap surfaces a context error.
+    pub fn run<F>(mut self, mut callback: F) -> Result<(), WindowError>
+    where
+        F: FnMut(&mut GlfwWindow, &[(f64, glfw::WindowEvent)]) -> ControlFlow,
+    {
+        loop {
+            self.glfw.poll_events();
+            let events: Vec<_> = glfw::flush_messages(&self.events).collect();
+
+            if let ControlFlow::Exit = callback(&mut self, &events) {
+                break;
+            }
+
+            if self.window.should_close() {
+                break;
+            }
+
+            self.swap_buffers()?;
+        }
+
+        Ok(())
+    }
+
+    fn swap_buffers(&mut self) -> Result<(), WindowError> {
+        self.window.swap_buffers();
+
+        let error = unsafe { gl::GetError() };
+        if error != gl::NO_ERROR {
+            return Err(WindowError::ContextError(error));
+        }
+
+        Ok(())
+    }
+}
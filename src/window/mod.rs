@@ -0,0 +1,54 @@
+use std::fmt;
+
+#[cfg(not(feature = "egl"))]
+mod glfw_backend;
+#[cfg(feature = "egl")]
+mod egl_backend;
+
+#[cfg(not(feature = "egl"))]
+pub use glfw_backend::GlfwWindow as Window;
+#[cfg(feature = "egl")]
+pub use egl_backend::EglWindow as Window;
+
+/// Everything that can go wrong creating a window/context or driving its event loop.
+/// Shared by every backend so callers don't need to know which one is active.
+#[derive(Debug)]
+pub enum WindowError {
+    /// GLFW itself failed to initialize (desktop backend only).
+    Init(glfw::InitError),
+    /// The windowing backend failed to create a surface/window.
+    CreationFailed,
+    /// EGL failed to initialize or create a context/surface (EGL backend only).
+    EglError(String),
+    /// `glGetError` reported a context error after swapping buffers.
+    ContextError(u32),
+}
+
+impl fmt::Display for WindowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WindowError::Init(e) => write!(f, "failed to initialize GLFW: {}", e),
+            WindowError::CreationFailed => write!(f, "failed to create a window/surface"),
+            WindowError::EglError(message) => write!(f, "EGL error: {}", message),
+            WindowError::ContextError(code) => {
+                write!(f, "OpenGL context error after buffer swap: 0x{:X}", code)
+            }
+        }
+    }
+}
+
+impl std::error::Error for WindowError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WindowError::Init(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Tells the event loop whether to keep driving.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlFlow {
+    Continue,
+    Exit,
+}